@@ -3,13 +3,21 @@ pub trait Accessor {
     const SEPARATOR : u8 = b'\t';
     type KeyType : ?Sized;
 
-    fn compare_lines(&self, line1: &[u8], line2: &[u8]) -> std::cmp::Ordering {
+    fn compare_lines(&self, line1: &[u8], line2: &[u8]) -> core::cmp::Ordering {
         let k1 = self.key(line1);
         let k2 = self.key(line2);
         k1.cmp(k2)
     }
 
-    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> std::cmp::Ordering;
+    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> core::cmp::Ordering;
+
+    /// The raw bytes of a query key, if this accessor's `KeyType` can be
+    /// compared against a Bloom filter built from `key()` bytes.
+    /// Accessors whose `KeyType` isn't itself a byte string (e.g.
+    /// `TsvParse`) return `None`, which disables filtering for them.
+    fn key_bytes<'a>(&self, _key: &'a Self::KeyType) -> Option<&'a [u8]> {
+        None
+    }
 
     /// Iterator over keys as strings.
     fn key<'a>(&self, line: &'a [u8]) -> &'a [u8] {
@@ -37,35 +45,39 @@ pub trait Accessor {
 pub struct TsvText<const KEY_COL : usize = 0>;
 
 #[derive(Default, Clone, Copy)]
-pub struct TsvParse<Ty : std::str::FromStr, const KEY_COL : usize> {
-    ty: std::marker::PhantomData<Ty>,
+pub struct TsvParse<Ty : core::str::FromStr, const KEY_COL : usize> {
+    ty: core::marker::PhantomData<Ty>,
 }
 
 impl<const KEY_COL : usize> Accessor for TsvText<KEY_COL>
 {
     const KEY_COLUMN : usize = KEY_COL;
     type KeyType = [u8];
-    
-    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> std::cmp::Ordering {
+
+    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> core::cmp::Ordering {
         let k1 = self.key(line);
         k1.cmp(k2)
     }
+
+    fn key_bytes<'a>(&self, key: &'a Self::KeyType) -> Option<&'a [u8]> {
+        Some(key)
+    }
 }
 
-impl<Ty : std::cmp::Ord + std::str::FromStr + Default + std::fmt::Display, const KEY_COL: usize> Accessor for TsvParse<Ty, KEY_COL> {
+impl<Ty : core::cmp::Ord + core::str::FromStr + Default + core::fmt::Display, const KEY_COL: usize> Accessor for TsvParse<Ty, KEY_COL> {
     const KEY_COLUMN : usize = KEY_COL;
     type KeyType = Ty;
 
-    fn compare_lines(&self, line1: &[u8], line2: &[u8]) -> std::cmp::Ordering {
-        let k1 = std::str::from_utf8(self.key(line1)).unwrap_or_default();
-        let k2 = std::str::from_utf8(self.key(line2)).unwrap_or_default();
+    fn compare_lines(&self, line1: &[u8], line2: &[u8]) -> core::cmp::Ordering {
+        let k1 = core::str::from_utf8(self.key(line1)).unwrap_or_default();
+        let k2 = core::str::from_utf8(self.key(line2)).unwrap_or_default();
         let k1 : Ty = k1.parse().unwrap_or_default();
         let k2 : Ty = k2.parse().unwrap_or_default();
         k1.cmp(&k2)
     }
     
-    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> std::cmp::Ordering {
-        let k1 = std::str::from_utf8(self.key(line)).unwrap_or_default();
+    fn compare_key(&self, line: &[u8], k2: &Self::KeyType) -> core::cmp::Ordering {
+        let k1 = core::str::from_utf8(self.key(line)).unwrap_or_default();
         let k1 : Ty = k1.parse().unwrap_or_default();
         k1.cmp(k2)
     }
@@ -0,0 +1,212 @@
+//! A minimal Aho-Corasick automaton used by [`crate::Table::scan_values`]
+//! to find every line whose target column contains any of several
+//! substring patterns in one linear pass, instead of one `find` per
+//! pattern per line.
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+const ROOT: usize = 0;
+
+/// Rough frequency rank of each byte in English prose (higher = rarer),
+/// used only to pick the cheapest `memchr` prefilter byte for a pattern
+/// set; nothing here depends on the ordering being precise. Letters are
+/// ranked most to least common as `etaoinshrdlcumwfgypbvkjxqz`.
+static BYTE_FREQUENCY: [u16; 256] = {
+    let mut freq = [100u16; 256];
+    freq[b' ' as usize] = 0;
+
+    let order = b"etaoinshrdlcumwfgypbvkjxqz";
+    let mut i = 0;
+    while i < order.len() {
+        freq[order[i] as usize] = 1 + i as u16;
+        freq[order[i].to_ascii_uppercase() as usize] = 1 + i as u16;
+        i += 1;
+    }
+
+    let mut d = b'0';
+    while d <= b'9' {
+        freq[d as usize] = 30;
+        d += 1;
+    }
+    freq
+};
+
+fn byte_frequency(byte: u8) -> u16 {
+    BYTE_FREQUENCY[byte as usize]
+}
+
+/// The index of the first occurrence of `byte` in `haystack`, or `None`.
+fn memchr(byte: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b == byte)
+}
+
+struct Node {
+    goto: BTreeMap<u8, usize>,
+    fail: usize,
+    is_match: bool,
+}
+
+/// A trie over a fixed set of byte-string patterns, with failure and
+/// output links computed so any one of them can be found in a haystack in
+/// a single pass, however many patterns there are.
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    /// A byte every pattern contains, chosen as the rarest (per
+    /// [`BYTE_FREQUENCY`]) among the bytes common to all of them, so a
+    /// haystack lacking it can be rejected with one `memchr` instead of
+    /// running the automaton. `None` if the patterns share no such byte
+    /// (including when any pattern is empty, since that matches anywhere).
+    rarest_required_byte: Option<u8>,
+}
+
+impl AhoCorasick {
+    /// Build an automaton over `patterns`.
+    pub fn build<'a, I>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        I::IntoIter: Clone,
+    {
+        let patterns = patterns.into_iter();
+        let rarest_required_byte = rarest_required_byte(patterns.clone());
+
+        let mut nodes = vec![Node {
+            goto: BTreeMap::new(),
+            fail: ROOT,
+            is_match: false,
+        }];
+
+        for pattern in patterns {
+            let mut state = ROOT;
+            for &byte in pattern {
+                state = match nodes[state].goto.get(&byte) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node {
+                            goto: BTreeMap::new(),
+                            fail: ROOT,
+                            is_match: false,
+                        });
+                        let next = nodes.len() - 1;
+                        nodes[state].goto.insert(byte, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].is_match = true;
+        }
+
+        // BFS from root to compute failure links: a node's failure link is
+        // the longest proper suffix of its path from root that is also a
+        // trie node, defaulting to root. Root's direct children have
+        // nothing shorter than the empty string to fall back to, so they
+        // fail straight to root; `is_match` inherits from the failure
+        // target so a node's output set includes every pattern that ends
+        // along its failure chain.
+        let mut queue = VecDeque::new();
+        let root_children: Vec<usize> = nodes[ROOT].goto.values().copied().collect();
+        for child in root_children {
+            queue.push_back(child);
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let transitions: Vec<(u8, usize)> = nodes[state]
+                .goto
+                .iter()
+                .map(|(&byte, &next)| (byte, next))
+                .collect();
+            for (byte, next) in transitions {
+                queue.push_back(next);
+
+                let mut fail = nodes[state].fail;
+                let fail_target = loop {
+                    if let Some(&candidate) = nodes[fail].goto.get(&byte) {
+                        break candidate;
+                    }
+                    if fail == ROOT {
+                        break ROOT;
+                    }
+                    fail = nodes[fail].fail;
+                };
+                nodes[next].fail = fail_target;
+                nodes[next].is_match = nodes[next].is_match || nodes[fail_target].is_match;
+            }
+        }
+
+        Self {
+            nodes,
+            rarest_required_byte,
+        }
+    }
+
+    /// Does `haystack` contain any of the patterns this automaton was
+    /// built from?
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        if let Some(byte) = self.rarest_required_byte {
+            if memchr(byte, haystack).is_none() {
+                return false;
+            }
+        }
+        self.walk(haystack)
+    }
+
+    /// Walk `haystack` byte by byte, following `goto` edges and falling
+    /// back along failure links on mismatch, until a state with a
+    /// non-empty output set is reached.
+    fn walk(&self, haystack: &[u8]) -> bool {
+        let mut state = ROOT;
+        if self.nodes[ROOT].is_match {
+            // An empty pattern matches even an empty haystack.
+            return true;
+        }
+        for &byte in haystack {
+            loop {
+                if let Some(&next) = self.nodes[state].goto.get(&byte) {
+                    state = next;
+                    break;
+                }
+                if state == ROOT {
+                    break;
+                }
+                state = self.nodes[state].fail;
+            }
+            if self.nodes[state].is_match {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The rarest byte common to every pattern, or `None` if they share none
+/// (or any pattern is empty, since an empty pattern matches everywhere and
+/// so admits no safe prefilter byte).
+fn rarest_required_byte<'a, I>(patterns: I) -> Option<u8>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut patterns = patterns.into_iter();
+    let first = patterns.next()?;
+    if first.is_empty() {
+        return None;
+    }
+    let mut common: BTreeSet<u8> = first.iter().copied().collect();
+
+    for pattern in patterns {
+        if pattern.is_empty() {
+            return None;
+        }
+        let bytes: BTreeSet<u8> = pattern.iter().copied().collect();
+        common = common.intersection(&bytes).copied().collect();
+        if common.is_empty() {
+            return None;
+        }
+    }
+
+    common.into_iter().max_by_key(|&byte| byte_frequency(byte))
+}
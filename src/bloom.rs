@@ -0,0 +1,130 @@
+//! A Bloom filter for quickly rejecting keys that cannot be present in a
+//! sorted [`crate::Table`], modeled on leveldb's filter block.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::error::Error;
+
+/// Bits allocated per key when a filter is built from scratch.
+#[cfg(feature = "std")]
+const BITS_PER_KEY: usize = 10;
+
+/// A fixed-size Bloom filter over byte-string keys.
+///
+/// Membership is tested with double hashing: `h_i = (h1 + i * h2) mod m`,
+/// where `h1` and `h2` are the two halves of a 64-bit FNV-1a hash of the key.
+/// This avoids computing `k` independent hashes per key/query.
+#[derive(Clone)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    m: u64,
+    k: u32,
+}
+
+impl BloomFilter {
+    /// Create an empty filter with `m` bits and `k` hash functions.
+    pub fn with_params(m: u64, k: u32) -> Self {
+        let m = m.max(8);
+        let bytes = m.div_ceil(8) as usize;
+        Self {
+            bits: vec![0u8; bytes],
+            m,
+            k: k.max(1),
+        }
+    }
+
+    /// Build a filter over the given keys, sizing `m` and `k` automatically:
+    /// ~10 bits per key, and `k = round(m/n * ln2)` hash functions.
+    /// Needs `std` for the `f64::round` used to size `k`; in `no_std` builds,
+    /// compute `m`/`k` ahead of time and use [`BloomFilter::with_params`].
+    #[cfg(feature = "std")]
+    pub fn build<'a, I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        I::IntoIter: Clone,
+    {
+        let iter = keys.into_iter();
+        let n = iter.clone().count().max(1);
+        let m = (n * BITS_PER_KEY).max(8) as u64;
+        let k = ((m as f64 / n as f64) * std::f64::consts::LN_2).round().max(1.0) as u32;
+
+        let mut filter = Self::with_params(m, k);
+        for key in iter {
+            filter.insert(key);
+        }
+        filter
+    }
+
+    /// Add a key to the filter.
+    pub fn insert(&mut self, key: &[u8]) {
+        let (mut h, delta) = self.hashes(key);
+        for _ in 0..self.k {
+            let bit = (h % self.m) as usize;
+            self.bits[bit / 8] |= 1 << (bit % 8);
+            h = h.wrapping_add(delta);
+        }
+    }
+
+    /// Returns `false` if `key` is definitely absent, `true` if it may be present.
+    pub fn might_contain(&self, key: &[u8]) -> bool {
+        let (mut h, delta) = self.hashes(key);
+        for _ in 0..self.k {
+            let bit = (h % self.m) as usize;
+            if self.bits[bit / 8] & (1 << (bit % 8)) == 0 {
+                return false;
+            }
+            h = h.wrapping_add(delta);
+        }
+        true
+    }
+
+    /// The two double-hashing seeds for `key`: `(h1, h2)`.
+    fn hashes(&self, key: &[u8]) -> (u64, u64) {
+        let h = fnv1a_64(key);
+        let h1 = h & 0xffff_ffff;
+        let h2 = (h >> 32) | 1; // keep the step odd, so never zero.
+        (h1, h2)
+    }
+
+    /// Serialize to a sidecar-file-friendly byte buffer: `m` and `k` as
+    /// little-endian integers, followed by the raw bit vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(12 + self.bits.len());
+        out.extend_from_slice(&self.m.to_le_bytes());
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.extend_from_slice(&self.bits);
+        out
+    }
+
+    /// Load a filter previously written by [`BloomFilter::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 12 {
+            return Err(Error::Truncated);
+        }
+        let m = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let k = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        let bits = data[12..].to_vec();
+        // `with_params` never produces `m < 8`; a header claiming otherwise
+        // is corrupt, and letting it through would divide by zero (or near
+        // enough to be useless) on the first `insert`/`might_contain`.
+        if m < 8 || (bits.len() as u64) < m.div_ceil(8) {
+            return Err(Error::Truncated);
+        }
+        Ok(Self { bits, m, k })
+    }
+}
+
+/// 64-bit FNV-1a hash.
+fn fnv1a_64(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
@@ -0,0 +1,218 @@
+//! Turn arbitrary unsorted TSV into a file [`crate::Table`] can binary
+//! search, the way leveldb's table builder produces a sorted SSTable from
+//! unsorted writes. Input is read in chunks bounded by a memory budget,
+//! each chunk is sorted in memory and spilled to a temp run file, and the
+//! runs are then merged with a k-way merge (the same cursor/heap shape as
+//! [`crate::merge`]) into the final sorted output.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use anyhow::{Context, Result};
+
+use crate::accessor::Accessor;
+
+/// Bytes of input buffered per sorted run before it's spilled to disk.
+const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+static RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Builds a sorted textdb file from unsorted input via external merge sort.
+pub struct TableBuilder<Access: Accessor> {
+    accessor: Access,
+    memory_budget: usize,
+    temp_dir: PathBuf,
+}
+
+impl<Access: Accessor + Clone> TableBuilder<Access> {
+    /// Create a builder with a 64MiB memory budget and the system temp
+    /// directory for intermediate sorted runs.
+    pub fn new(accessor: Access) -> Self {
+        Self {
+            accessor,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Bound the total size of lines buffered per sorted run.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Directory used for intermediate sorted-run files.
+    pub fn with_temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Read unsorted lines from `input` and write a sorted,
+    /// trailing-newline-normalized file to `output`, ready for
+    /// [`crate::Table::new`]. The result is checked with
+    /// [`crate::Table::is_sorted`] before returning.
+    pub fn build<P: AsRef<Path>>(&self, input: P, output: P) -> Result<()> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        let runs = self.write_sorted_runs(input)?;
+        self.merge_runs(&runs, output)?;
+
+        let map = crate::maps::SafeMemoryMap::from_file(output)
+            .with_context(|| format!("Unable to reopen {output:?} for verification"))?;
+        let table = crate::Table::new(map, self.accessor.clone());
+        anyhow::ensure!(
+            table.is_sorted().expect("is_sorted is infallible with std"),
+            "TableBuilder produced an unsorted file: {output:?}"
+        );
+        Ok(())
+    }
+
+    /// Read `input` in memory-budget-sized chunks, sort each chunk by
+    /// `accessor.compare_lines`, and spill it to its own temp run file.
+    fn write_sorted_runs(&self, input: &Path) -> Result<Vec<PathBuf>> {
+        let file =
+            File::open(input).with_context(|| format!("Unable to open {input:?}"))?;
+        let reader = BufReader::new(file);
+
+        let mut runs = Vec::new();
+        let mut chunk: Vec<Vec<u8>> = Vec::new();
+        let mut chunk_bytes = 0usize;
+
+        for line in reader.split(b'\n') {
+            let line = line?;
+            chunk_bytes += line.len();
+            chunk.push(line);
+            if chunk_bytes >= self.memory_budget {
+                runs.push(self.spill(&mut chunk)?);
+                chunk_bytes = 0;
+            }
+        }
+        if !chunk.is_empty() {
+            runs.push(self.spill(&mut chunk)?);
+        }
+        Ok(runs)
+    }
+
+    /// Sort a chunk in memory and write it to a fresh run file, emptying
+    /// `chunk` so its buffer can be reused for the next one.
+    fn spill(&self, chunk: &mut Vec<Vec<u8>>) -> Result<PathBuf> {
+        chunk.sort_by(|a, b| self.accessor.compare_lines(a, b));
+
+        let path = next_run_path(&self.temp_dir);
+        let file = File::create(&path).with_context(|| format!("Unable to create {path:?}"))?;
+        let mut writer = BufWriter::new(file);
+        for line in chunk.drain(..) {
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(path)
+    }
+
+    /// K-way merge the sorted runs into `output`, then remove the runs.
+    fn merge_runs(&self, runs: &[PathBuf], output: &Path) -> Result<()> {
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+        for (shard, path) in runs.iter().enumerate() {
+            if let Some(cursor) = RunCursor::open(&self.accessor, shard, path)? {
+                heap.push(cursor);
+            }
+        }
+
+        let out_file =
+            File::create(output).with_context(|| format!("Unable to create {output:?}"))?;
+        let mut writer = BufWriter::new(out_file);
+
+        while let Some(mut cursor) = heap.pop() {
+            writer.write_all(&cursor.line)?;
+            writer.write_all(b"\n")?;
+            if cursor.advance()? {
+                heap.push(cursor);
+            }
+        }
+        writer.flush()?;
+
+        for path in runs {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(())
+    }
+}
+
+/// A unique path for a new sorted-run file in `dir`.
+fn next_run_path(dir: &Path) -> PathBuf {
+    let n = RUN_COUNTER.fetch_add(1, AtomicOrdering::Relaxed);
+    dir.join(format!("textdb-run-{}-{n}.tmp", std::process::id()))
+}
+
+/// One sorted run's position in the k-way merge: the line it currently
+/// points at, and the reader for the rest of the run.
+struct RunCursor<'a, Access: Accessor> {
+    accessor: &'a Access,
+    shard: usize,
+    reader: BufReader<File>,
+    line: Vec<u8>,
+}
+
+impl<'a, Access: Accessor> RunCursor<'a, Access> {
+    /// Open `path` and position the cursor at its first line, or return
+    /// `None` if the run is empty.
+    fn open(accessor: &'a Access, shard: usize, path: &Path) -> Result<Option<Self>> {
+        let reader =
+            BufReader::new(File::open(path).with_context(|| format!("Unable to open {path:?}"))?);
+        let mut cursor = Self {
+            accessor,
+            shard,
+            reader,
+            line: Vec::new(),
+        };
+        Ok(if cursor.advance()? {
+            Some(cursor)
+        } else {
+            None
+        })
+    }
+
+    /// Read the next line into `self.line`, returning `false` at EOF.
+    fn advance(&mut self) -> Result<bool> {
+        self.line.clear();
+        let n = self.reader.read_until(b'\n', &mut self.line)?;
+        if n == 0 {
+            return Ok(false);
+        }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
+        }
+        Ok(true)
+    }
+}
+
+impl<Access: Accessor> PartialEq for RunCursor<'_, Access> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<Access: Accessor> Eq for RunCursor<'_, Access> {}
+
+impl<Access: Accessor> PartialOrd for RunCursor<'_, Access> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Access: Accessor> Ord for RunCursor<'_, Access> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse the key comparison (and
+        // tie-break on run index) so the smallest key among all runs is
+        // popped first, as in `crate::merge::Cursor`.
+        self.accessor
+            .compare_lines(&self.line, &other.line)
+            .reverse()
+            .then_with(|| other.shard.cmp(&self.shard))
+    }
+}
@@ -0,0 +1,24 @@
+//! A crate-local error type for the APIs that remain available without
+//! `std` (`anyhow`'s `std::error::Error` bound isn't usable there).
+
+use core::fmt;
+
+/// Errors produced by `textdb`'s always-available, `no_std`-friendly API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A serialized buffer (e.g. a Bloom filter sidecar) was too short for
+    /// its own header, or shorter than the header declares it to be, or
+    /// its header is invalid (e.g. a Bloom filter claiming fewer than 8 bits).
+    Truncated,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "buffer is truncated"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
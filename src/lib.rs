@@ -1,9 +1,25 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
 
 pub mod maps;
 pub mod accessor;
+pub mod aho_corasick;
+pub mod bloom;
+#[cfg(feature = "std")]
+pub mod builder;
+pub mod error;
+pub mod merge;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
 
 use accessor::{Accessor, TsvText};
+use aho_corasick::AhoCorasick;
+use bloom::BloomFilter;
+use error::Error;
 use maps::{MemoryMap, SafeMemoryMap};
 
 
@@ -12,6 +28,7 @@ use maps::{MemoryMap, SafeMemoryMap};
 pub struct Table<Map: MemoryMap, Access : Accessor> {
     accessor: Access,
     map: Map,
+    filter: Option<BloomFilter>,
 }
 
 /// A line from a memory mapped text database.
@@ -20,6 +37,40 @@ pub struct Line<'a, Map: MemoryMap, Access : Accessor> {
     line: &'a [u8],
 }
 
+type LineSplit<'a> = core::slice::Split<'a, u8, fn(&u8) -> bool>;
+
+fn split_lines(bytes: &[u8]) -> LineSplit<'_> {
+    bytes.split((|b: &u8| *b == b'\n') as fn(&u8) -> bool)
+}
+
+/// The lines in a byte range, or none at all: `get_matching_lines` and
+/// `get_range` both need to report "no match" as a genuinely empty
+/// iterator rather than splitting an empty byte range, which yields one
+/// (empty) element instead of zero.
+struct LineRange<'a, Map: MemoryMap, Access: Accessor> {
+    textdb: &'a Table<Map, Access>,
+    lines: Option<LineSplit<'a>>,
+}
+
+impl<'a, Map: MemoryMap, Access: Accessor> LineRange<'a, Map, Access> {
+    fn empty(textdb: &'a Table<Map, Access>) -> Self {
+        Self { textdb, lines: None }
+    }
+
+    fn of(textdb: &'a Table<Map, Access>, bytes: &'a [u8]) -> Self {
+        Self { textdb, lines: Some(split_lines(bytes)) }
+    }
+}
+
+impl<'a, Map: MemoryMap, Access: Accessor> Iterator for LineRange<'a, Map, Access> {
+    type Item = Line<'a, Map, Access>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.as_mut()?.next()?;
+        Some(Line { textdb: self.textdb, line })
+    }
+}
+
 impl Table<SafeMemoryMap, TsvText> {
     // Make a table from an owned string.
     pub fn text_tsv_from_string(text: String) -> Self {
@@ -42,18 +93,49 @@ impl<Access : Accessor, Map: MemoryMap> Table<Map, Access> {
     pub fn new(map: Map, accessor: Access) -> Self {
         Self {
             map,
-            accessor
+            accessor,
+            filter: None,
         }
     }
 
+    /// Make a new memory mapped text database with a Bloom filter over its
+    /// keys, so [`Table::get_matching_lines`] can reject absent keys in
+    /// O(1) before falling back to binary search.
+    #[cfg(feature = "std")]
+    pub fn with_filter(map: Map, accessor: Access) -> Self {
+        let mut table = Self::new(map, accessor);
+        table.build_filter();
+        table
+    }
+
+    /// (Re)build the Bloom filter by scanning every line once.
+    /// Note: On large files (> 1TB) this may take some time to run.
+    #[cfg(feature = "std")]
+    pub fn build_filter(&mut self) {
+        let bytes = self.map.bytes();
+        let keys = bytes.split(|b| *b == b'\n').map(|line| self.accessor.key(line));
+        self.filter = Some(BloomFilter::build(keys));
+    }
+
+    /// The table's Bloom filter, if one has been built, so it can be
+    /// persisted to a sidecar file instead of rescanning the table.
+    pub fn filter(&self) -> Option<&BloomFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Install a Bloom filter, e.g. one loaded from a sidecar file.
+    pub fn set_filter(&mut self, filter: BloomFilter) {
+        self.filter = Some(filter);
+    }
+
     /// Return true if the database is sorted.
     /// Note: On large files (> 1TB) this may take some time to run.
-    pub fn is_sorted(&self) -> anyhow::Result<bool> {
+    pub fn is_sorted(&self) -> Result<bool, Error> {
         let bytes = self.map.bytes();
         let mut iter = bytes.split(|b| *b == b'\n');
         let mut prev_line = iter.next().unwrap_or_default();
         for line in iter {
-            if self.accessor.compare_lines(prev_line, line) == std::cmp::Ordering::Greater {
+            if self.accessor.compare_lines(prev_line, line) == core::cmp::Ordering::Greater {
                 return Ok(false);
             }
             prev_line = line;
@@ -63,16 +145,16 @@ impl<Access : Accessor, Map: MemoryMap> Table<Map, Access> {
 
     /// Get all the keys as strings.
     /// Note: On large files (> 1TB) this may take some time to run.
-    pub fn keys(&self) -> impl Iterator<Item=Result<&str, std::str::Utf8Error>> {
+    pub fn keys(&self) -> impl Iterator<Item=Result<&str, core::str::Utf8Error>> {
         self.map.bytes().split(|b| *b == b'\n').map(|line| {
-            std::str::from_utf8(self.accessor.key(line))
+            core::str::from_utf8(self.accessor.key(line))
         })
     }
 
     /// Get one column as strings.
-    pub fn cols(&self, i: usize) -> impl Iterator<Item=Result<&str, std::str::Utf8Error>> {
+    pub fn cols(&self, i: usize) -> impl Iterator<Item=Result<&str, core::str::Utf8Error>> {
         self.map.bytes().split(|b| *b == b'\n').map(move |line| {
-            std::str::from_utf8(self.accessor.col(line, i))
+            core::str::from_utf8(self.accessor.col(line, i))
         })
     }
 
@@ -84,14 +166,17 @@ impl<Access : Accessor, Map: MemoryMap> Table<Map, Access> {
         assert!(end <= max);
         assert!(end >= start);
 
-        // Trim the newline.
-        let line_end = if end != 0 && bytes[end-1] == b'\n' { end-1 } else { end };
+        // Trim the newline. `end > start` guards an empty (zero-length)
+        // line: `bytes[end-1]` there would be the byte just *before* this
+        // range, which can belong to the previous line's own newline and
+        // must not be mistaken for this line's terminator.
+        let line_end = if end > start && bytes[end-1] == b'\n' { end-1 } else { end };
         let line = &bytes[start..line_end];
         (start, end, line)
     }
 
     /// Return an iterator over all matching lines for a certain key.
-    pub fn get_matching_lines(&self, key: &Access::KeyType) -> impl Iterator<Item=Line<Map, Access>> {
+    pub fn get_matching_lines(&self, key: &Access::KeyType) -> impl Iterator<Item=Line<'_, Map, Access>> {
         let bytes = self.map.bytes();
 
         // Always the start of a line.
@@ -99,86 +184,178 @@ impl<Access : Accessor, Map: MemoryMap> Table<Map, Access> {
 
         // Always the end of a line (not counting the newline).
         let mut max = bytes.len();
-        loop {
-            let mid = min + (max - min) / 2;
-            let (start, end, line) = Self::find_line_at(bytes, min, max, mid);
 
-            #[cfg(test)]
-            {
-                let cmp = self.accessor.compare_key(line, key);
-                let range = std::str::from_utf8(&bytes[min..max]).unwrap();
-                println!("min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", std::str::from_utf8(line).unwrap());
-            }
+        // If we have a filter and can get raw bytes for this key, reject
+        // absent keys in O(1) without touching the mmap's binary-searched
+        // range. A "maybe present" result falls through to the binary
+        // search below exactly as before.
+        let maybe_present = match (&self.filter, self.accessor.key_bytes(key)) {
+            (Some(filter), Some(key_bytes)) => filter.might_contain(key_bytes),
+            _ => true,
+        };
+
+        if maybe_present {
+            loop {
+                if min >= max {
+                    // The range has narrowed to nothing without ever
+                    // finding `key`: it isn't in the data. Stop here
+                    // instead of re-probing an empty range, which would
+                    // assert (no forward progress is possible) or slice
+                    // out of bounds.
+                    break;
+                }
 
-            match self.accessor.compare_key(line, key) {
-                // line < key: 
-                std::cmp::Ordering::Less => {
-                    // Ensure forward progress by moving min up one line.
-                    assert!(min != end);
-                    min = end;
+                let mid = min + (max - min) / 2;
+                let (start, end, line) = Self::find_line_at(bytes, min, max, mid);
+
+                #[cfg(test)]
+                {
+                    let cmp = self.accessor.compare_key(line, key);
+                    let range = core::str::from_utf8(&bytes[min..max]).unwrap();
+                    println!("min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", core::str::from_utf8(line).unwrap());
                 }
-                std::cmp::Ordering::Equal => {
-                    let (_start, end, line) = Self::find_line_at(bytes, min, max, min);
-                    #[cfg(test)]
-                    {
-                        assert_eq!(start, min);
-                        let range = std::str::from_utf8(&bytes[min..max]).unwrap();
-                        let cmp = self.accessor.compare_key(line, key);
-                        println!("=min min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", std::str::from_utf8(line).unwrap());
+
+                match self.accessor.compare_key(line, key) {
+                    // line < key: 
+                    core::cmp::Ordering::Less => {
+                        // Ensure forward progress by moving min up one line.
+                        assert!(min != end);
+                        min = end;
                     }
-                    let mut min_is_equal = false;
-                    match self.accessor.compare_key(line, key) {
-                        std::cmp::Ordering::Less => {
-                            assert!(min != end);
-                            min = end;
+                    core::cmp::Ordering::Equal => {
+                        let (_start, end, line) = Self::find_line_at(bytes, min, max, min);
+                        #[cfg(test)]
+                        {
+                            assert_eq!(start, min);
+                            let range = core::str::from_utf8(&bytes[min..max]).unwrap();
+                            let cmp = self.accessor.compare_key(line, key);
+                            println!("=min min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", core::str::from_utf8(line).unwrap());
                         }
-                        std::cmp::Ordering::Equal => {
-                            min_is_equal = true;
-                        }
-                        std::cmp::Ordering::Greater => {
-                            // Not sorted!
-                            max = min;
-                            break;
+                        let mut min_is_equal = false;
+                        match self.accessor.compare_key(line, key) {
+                            core::cmp::Ordering::Less => {
+                                assert!(min != end);
+                                min = end;
+                            }
+                            core::cmp::Ordering::Equal => {
+                                min_is_equal = true;
+                            }
+                            core::cmp::Ordering::Greater => {
+                                // Not sorted!
+                                max = min;
+                                break;
+                            }
                         }
-                    }
 
-                    let (start, _end, line) = Self::find_line_at(bytes, min, max, max-1);
-                    #[cfg(test)]
-                    {
-                        assert_eq!(_end, max);
-                        let range = std::str::from_utf8(&bytes[min..max]).unwrap();
-                        let cmp = self.accessor.compare_key(line, key);
-                        println!("=max min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", std::str::from_utf8(line).unwrap());
-                    }
-                    match self.accessor.compare_key(line, key) {
-                        std::cmp::Ordering::Less => {
-                            // Not sorted!
-                            max = min;
-                            break;
+                        let (start, _end, line) = Self::find_line_at(bytes, min, max, max-1);
+                        #[cfg(test)]
+                        {
+                            assert_eq!(_end, max);
+                            let range = core::str::from_utf8(&bytes[min..max]).unwrap();
+                            let cmp = self.accessor.compare_key(line, key);
+                            println!("=max min={min} mid={mid} max={max} line ={:?} cmp={cmp:?} r={range:?}", core::str::from_utf8(line).unwrap());
                         }
-                        std::cmp::Ordering::Equal => {
-                            if min_is_equal {
-                                // Sucess, both min and max are equal.
-                                // Trim the range.
-                                max = if max != 0 && bytes[max-1] == b'\n' { max-1 } else { max };
+                        match self.accessor.compare_key(line, key) {
+                            core::cmp::Ordering::Less => {
+                                // Not sorted!
+                                max = min;
                                 break;
                             }
-                        }
-                        std::cmp::Ordering::Greater => {
-                            // Ensure forward progress by moving max down one.
-                            assert!(max != start);
-                            max = start;
+                            core::cmp::Ordering::Equal => {
+                                if min_is_equal {
+                                    // Sucess, both min and max are equal.
+                                    // Trim the range.
+                                    max = if max != 0 && bytes[max-1] == b'\n' { max-1 } else { max };
+                                    break;
+                                }
+                            }
+                            core::cmp::Ordering::Greater => {
+                                // Ensure forward progress by moving max down one.
+                                assert!(max != start);
+                                max = start;
+                            }
                         }
                     }
+                    core::cmp::Ordering::Greater => {
+                        assert!(max != start);
+                        max = start;
+                    }
                 }
-                std::cmp::Ordering::Greater => {
-                    assert!(max != start);
-                    max = start;
-                }
             }
+        } else {
+            // The filter reported this key is definitely absent.
+            max = min;
+        }
+
+        if min == max {
+            LineRange::empty(self)
+        } else {
+            LineRange::of(self, &bytes[min..max])
+        }
+    }
+
+    /// The byte offset of the first line whose key is `>= key`, snapped
+    /// onto a line boundary by `find_line_at`, or the length of the file
+    /// if every line sorts before `key`.
+    fn lower_bound(&self, key: &Access::KeyType) -> usize {
+        let bytes = self.map.bytes();
+        let mut min = 0;
+        let mut max = bytes.len();
+        while min < max {
+            let mid = min + (max - min) / 2;
+            let (start, end, line) = Self::find_line_at(bytes, min, max, mid);
+            if self.accessor.compare_key(line, key) == core::cmp::Ordering::Less {
+                // Ensure forward progress by moving min up one line.
+                assert!(min != end);
+                min = end;
+            } else {
+                // Ensure forward progress by moving max down one line.
+                assert!(max != start);
+                max = start;
+            }
+        }
+        min
+    }
+
+    /// Return an iterator over every line whose key falls in `[lo, hi)`,
+    /// mirroring leveldb's seek-based range iteration. `get_matching_lines`
+    /// is the special case of this with `lo == hi` extended to include it,
+    /// i.e. `[key, key]` rather than `[key, key)`.
+    ///
+    /// Reversed bounds (`lo > hi`), and any inconsistency `lower_bound`
+    /// turns up from unsorted input, land `upper` before `lower`; both are
+    /// treated the same as a genuinely empty range rather than indexing
+    /// out of bounds.
+    pub fn get_range(&self, lo: &Access::KeyType, hi: &Access::KeyType) -> impl Iterator<Item=Line<'_, Map, Access>> {
+        let bytes = self.map.bytes();
+        let lower = self.lower_bound(lo);
+        let upper = self.lower_bound(hi).max(lower);
+
+        // `upper` sits right after the newline that ends the last line in
+        // range (unless it's end-of-file); trim it so splitting doesn't
+        // yield a trailing empty line.
+        let upper = if upper > lower && bytes[upper-1] == b'\n' { upper-1 } else { upper };
+
+        if lower == upper {
+            LineRange::empty(self)
+        } else {
+            LineRange::of(self, &bytes[lower..upper])
         }
+    }
 
-        bytes[min..max].split(|b| *b == b'\n').map(|line| {
+    /// Return every line whose column `col` contains any of `patterns`, in
+    /// a single linear pass over the mmap via an Aho-Corasick automaton
+    /// built once from all the patterns. Unlike `get_matching_lines` and
+    /// `get_range`, this works on any column, not just the sorted key.
+    pub fn scan_values<'a, I>(&'a self, patterns: I, col: usize) -> impl Iterator<Item=Line<'a, Map, Access>>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        I::IntoIter: Clone,
+    {
+        let automaton = AhoCorasick::build(patterns);
+        self.map.bytes().split(|b| *b == b'\n').filter(move |line| {
+            automaton.is_match(self.accessor.col(line, col))
+        }).map(move |line| {
             Line {
                 textdb: self,
                 line,
@@ -189,17 +366,17 @@ impl<Access : Accessor, Map: MemoryMap> Table<Map, Access> {
 
 impl<'a, Access : Accessor, Map: MemoryMap> Line<'a, Map, Access> {
     /// Get the key of this line as a string.
-    pub fn key(&self) -> Result<&str, std::str::Utf8Error> {
-        std::str::from_utf8(self.textdb.accessor.key(self.line))
+    pub fn key(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.textdb.accessor.key(self.line))
     }
 
     /// Get a column of this line as a string.
-    pub fn col(&self, i: usize) -> Result<&str, std::str::Utf8Error> {
-        std::str::from_utf8(self.textdb.accessor.col(self.line, i))
+    pub fn col(&self, i: usize) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.textdb.accessor.col(self.line, i))
     }
 
-    pub fn line(&self) ->  Result<&str, std::str::Utf8Error> {
-        std::str::from_utf8(self.line)
+    pub fn line(&self) ->  Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.line)
     }
 }
 
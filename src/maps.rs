@@ -1,8 +1,14 @@
+#[cfg(feature = "std")]
 use std::path::Path;
 
-#[cfg(feature="mmap")]
+#[cfg(all(feature = "mmap", feature = "std"))]
 use {memmap2::Mmap, anyhow::Context, std::fs::File};
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
 pub trait MemoryMap {
     fn bytes(&self) -> &[u8];
 }
@@ -10,7 +16,7 @@ pub trait MemoryMap {
 
 /// An unsafe, high performance memory map
 /// Unsafe because someone else could come and truncate your file!
-#[cfg(feature="mmap")]
+#[cfg(all(feature = "mmap", feature = "std"))]
 pub struct UnsafeMemoryMap {
     mmap: Mmap,
 }
@@ -20,7 +26,7 @@ pub struct SafeMemoryMap {
     mmap: Vec<u8>,
 }
 
-#[cfg(feature="mmap")]
+#[cfg(all(feature = "mmap", feature = "std"))]
 impl UnsafeMemoryMap {
     pub fn new<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let path = path.as_ref();
@@ -38,7 +44,7 @@ impl UnsafeMemoryMap {
     }
 }
 
-#[cfg(feature="mmap")]
+#[cfg(all(feature = "mmap", feature = "std"))]
 impl MemoryMap for UnsafeMemoryMap {
     fn bytes(&self) -> &[u8] {
         self.mmap.as_ref()
@@ -47,6 +53,7 @@ impl MemoryMap for UnsafeMemoryMap {
 
 impl SafeMemoryMap {
     /// Create a safe memory map from a file.
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let path = path.as_ref();
         let string = std::fs::read_to_string(path)?;
@@ -0,0 +1,204 @@
+//! Combine several individually-sorted tables that share one `Accessor`
+//! into a single globally-sorted logical table, the way leveldb's merging
+//! iterator unifies several sorted runs without physically concatenating
+//! and re-sorting them.
+
+use core::cmp::Ordering;
+
+#[cfg(feature = "std")]
+use std::collections::BinaryHeap;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, collections::BinaryHeap, vec::Vec};
+
+use crate::accessor::Accessor;
+use crate::maps::MemoryMap;
+use crate::{Line, Table};
+
+type LineSplit<'a> = core::slice::Split<'a, u8, fn(&u8) -> bool>;
+
+fn split_lines(bytes: &[u8]) -> LineSplit<'_> {
+    bytes.split((|b: &u8| *b == b'\n') as fn(&u8) -> bool)
+}
+
+/// Several sorted shard files, sharing one `Accessor`, presented as a
+/// single logical table sorted by the same key.
+pub struct MergedTable<Map: MemoryMap, Access: Accessor> {
+    tables: Vec<Table<Map, Access>>,
+}
+
+impl<Map: MemoryMap, Access: Accessor> MergedTable<Map, Access> {
+    /// Merge several shards into one logical table. The shards are not
+    /// required to be disjoint, only individually sorted by `accessor`.
+    pub fn new(tables: Vec<Table<Map, Access>>) -> Self {
+        Self { tables }
+    }
+
+    /// Iterate every line across all shards, in globally sorted order.
+    pub fn iter(&self) -> MergeIter<'_, Map, Access> {
+        let mut heap = BinaryHeap::with_capacity(self.tables.len());
+        for (shard, table) in self.tables.iter().enumerate() {
+            if let Some(cursor) = Cursor::new(&table.accessor, shard, table.map.bytes()) {
+                heap.push(cursor);
+            }
+        }
+        MergeIter { tables: &self.tables, heap }
+    }
+
+    /// Return every matching line for `key` across all shards, in sorted
+    /// order, by merging each shard's own `get_matching_lines` iterator.
+    pub fn get_matching_lines<'a>(&'a self, key: &'a Access::KeyType) -> MergeMatches<'a, Map, Access> {
+        let mut heap = BinaryHeap::with_capacity(self.tables.len());
+        for (shard, table) in self.tables.iter().enumerate() {
+            let rest = Box::new(table.get_matching_lines(key));
+            if let Some(cursor) = MatchCursor::new(&table.accessor, shard, rest) {
+                heap.push(cursor);
+            }
+        }
+        MergeMatches { heap }
+    }
+}
+
+/// One shard's position in the k-way merge over every line: the accessor
+/// used to compare it against its peers, the remaining lines in the
+/// shard, and the line the cursor currently points at.
+struct Cursor<'a, Access: Accessor> {
+    accessor: &'a Access,
+    shard: usize,
+    rest: LineSplit<'a>,
+    line: &'a [u8],
+}
+
+impl<'a, Access: Accessor> Cursor<'a, Access> {
+    /// Position a cursor at the shard's first line, or return `None` if the
+    /// shard is empty: `split` always yields at least one (possibly empty)
+    /// slice even for empty input, which would otherwise be mistaken for a
+    /// genuine line with an empty key.
+    fn new(accessor: &'a Access, shard: usize, bytes: &'a [u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            return None;
+        }
+        let mut rest = split_lines(bytes);
+        let line = rest.next().unwrap_or_default();
+        Some(Self { accessor, shard, rest, line })
+    }
+
+    fn advance(mut self) -> Option<Self> {
+        let line = self.rest.next()?;
+        self.line = line;
+        Some(self)
+    }
+}
+
+impl<Access: Accessor> PartialEq for Cursor<'_, Access> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<Access: Accessor> Eq for Cursor<'_, Access> {}
+
+impl<Access: Accessor> PartialOrd for Cursor<'_, Access> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Access: Accessor> Ord for Cursor<'_, Access> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse the key comparison (and
+        // tie-break on shard index) so the smallest key among all cursors
+        // is popped first.
+        self.accessor.compare_lines(self.line, other.line).reverse()
+            .then_with(|| other.shard.cmp(&self.shard))
+    }
+}
+
+/// Iterator over every line of a [`MergedTable`] in globally sorted order.
+pub struct MergeIter<'a, Map: MemoryMap, Access: Accessor> {
+    tables: &'a [Table<Map, Access>],
+    heap: BinaryHeap<Cursor<'a, Access>>,
+}
+
+impl<'a, Map: MemoryMap, Access: Accessor> Iterator for MergeIter<'a, Map, Access> {
+    type Item = Line<'a, Map, Access>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cursor = self.heap.pop()?;
+        let shard = cursor.shard;
+        let line = cursor.line;
+        if let Some(next_cursor) = cursor.advance() {
+            self.heap.push(next_cursor);
+        }
+        Some(Line { textdb: &self.tables[shard], line })
+    }
+}
+
+/// One shard's position in the k-way merge over a single key's matching
+/// lines: the accessor used to compare it against its peers, the line the
+/// cursor currently points at, and the rest of that shard's matches.
+struct MatchCursor<'a, Map: MemoryMap, Access: Accessor> {
+    accessor: &'a Access,
+    shard: usize,
+    line: Line<'a, Map, Access>,
+    rest: Box<dyn Iterator<Item = Line<'a, Map, Access>> + 'a>,
+}
+
+impl<'a, Map: MemoryMap, Access: Accessor> MatchCursor<'a, Map, Access> {
+    fn new(accessor: &'a Access, shard: usize, mut rest: Box<dyn Iterator<Item = Line<'a, Map, Access>> + 'a>) -> Option<Self> {
+        let line = rest.next()?;
+        Some(Self { accessor, shard, line, rest })
+    }
+
+    /// Advance to the next match in this shard, returning the line the
+    /// cursor was pointing at before advancing.
+    fn advance(&mut self) -> Option<Line<'a, Map, Access>> {
+        let next = self.rest.next()?;
+        Some(core::mem::replace(&mut self.line, next))
+    }
+}
+
+impl<Map: MemoryMap, Access: Accessor> PartialEq for MatchCursor<'_, Map, Access> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<Map: MemoryMap, Access: Accessor> Eq for MatchCursor<'_, Map, Access> {}
+
+impl<Map: MemoryMap, Access: Accessor> PartialOrd for MatchCursor<'_, Map, Access> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Map: MemoryMap, Access: Accessor> Ord for MatchCursor<'_, Map, Access> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for the same reason as `Cursor::cmp`: smallest first out
+        // of a max-heap, shard index as a stable tie-break since every
+        // match shares the same key.
+        self.accessor.compare_lines(self.line.line, other.line.line).reverse()
+            .then_with(|| other.shard.cmp(&self.shard))
+    }
+}
+
+/// Iterator over every matching line for a key across all shards of a
+/// [`MergedTable`], in sorted order.
+pub struct MergeMatches<'a, Map: MemoryMap, Access: Accessor> {
+    heap: BinaryHeap<MatchCursor<'a, Map, Access>>,
+}
+
+impl<'a, Map: MemoryMap, Access: Accessor> Iterator for MergeMatches<'a, Map, Access> {
+    type Item = Line<'a, Map, Access>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut cursor = self.heap.pop()?;
+        match cursor.advance() {
+            Some(prev_line) => {
+                self.heap.push(cursor);
+                Some(prev_line)
+            }
+            // This shard is exhausted; yield its last line and drop the cursor.
+            None => Some(cursor.line),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use textdb::{accessor, maps, Table};
+use textdb::{accessor, bloom::BloomFilter, builder::TableBuilder, maps, merge::MergedTable, Table};
 
 #[test]
 fn test_tsv_text() {
@@ -118,3 +118,238 @@ fn test_get_matching_lines_2() {
     }
 
 }
+
+#[test]
+fn test_get_matching_lines_with_filter() {
+    let text = "A\nB\nC\nC\nD\nE\nF\nF\nF\nF\nF\nG\nH\nI\nJ\nK\nL\n";
+    let accessor = accessor::TsvText::<0>::default();
+    let map = maps::SafeMemoryMap::from_str(text);
+    let textdb = Table::with_filter(map, accessor);
+
+    assert!(textdb.is_sorted().unwrap());
+    assert!(textdb.filter().is_some());
+
+    assert_eq!(textdb.get_matching_lines("F".as_bytes()).count(), 5);
+    assert_eq!(textdb.get_matching_lines("C".as_bytes()).count(), 2);
+
+    // "M" is absent: the filter rejects it in O(1), so the binary search
+    // never runs, and no lines are returned.
+    assert_eq!(textdb.get_matching_lines("M".as_bytes()).count(), 0);
+}
+
+#[test]
+fn test_bloom_filter_sidecar_round_trip() {
+    // The filter is meant to be built once, written to a sidecar file next
+    // to the table, and reloaded on a later run via `to_bytes`/`from_bytes`
+    // instead of rescanning every line. Exercise that exact round trip.
+    let text = "A\nB\nC\nC\nD\nE\nF\nF\nF\nF\nF\nG\nH\nI\nJ\nK\nL\n";
+    let accessor = accessor::TsvText::<0>::default();
+    let map = maps::SafeMemoryMap::from_str(text);
+    let mut textdb = Table::new(map, accessor);
+
+    textdb.build_filter();
+    let bytes = textdb.filter().unwrap().to_bytes();
+
+    let restored = BloomFilter::from_bytes(&bytes).unwrap();
+    textdb.set_filter(restored);
+
+    assert!(textdb.filter().unwrap().might_contain("F".as_bytes()));
+    assert!(textdb.filter().unwrap().might_contain("C".as_bytes()));
+
+    assert_eq!(textdb.get_matching_lines("F".as_bytes()).count(), 5);
+    assert_eq!(textdb.get_matching_lines("C".as_bytes()).count(), 2);
+    assert_eq!(textdb.get_matching_lines("M".as_bytes()).count(), 0);
+}
+
+#[test]
+fn test_bloom_filter_from_bytes_rejects_zero_m() {
+    // `with_params`/`build` always clamp `m` to at least 8; a sidecar file
+    // claiming `m == 0` is corrupt and must be rejected here rather than
+    // loaded and left to divide by zero on the first `might_contain` call.
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.push(0u8);
+
+    assert!(BloomFilter::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn test_get_matching_lines_absent_key_without_filter() {
+    // Without a filter, the same absent-key query falls through to the
+    // binary search itself, which must also report zero matches rather
+    // than panicking or yielding a spurious empty line.
+    let text = "A\nB\nC\nC\nD\nE\nF\nF\nF\nF\nF\nG\nH\nI\nJ\nK\nL\n";
+    let textdb = Table::text_tsv_from_str(text);
+
+    assert!(textdb.filter().is_none());
+    assert_eq!(textdb.get_matching_lines("M".as_bytes()).count(), 0);
+    assert_eq!(textdb.get_matching_lines("0".as_bytes()).count(), 0);
+}
+
+#[test]
+fn test_get_range() {
+    let text = "A\nB\nC\nC\nD\nE\nF\nF\nF\nF\nF\nG\nH\nI\nJ\nK\nL\n";
+    let textdb = Table::text_tsv_from_str(text);
+
+    assert!(textdb.is_sorted().unwrap());
+
+    let keys = |lo: &str, hi: &str| {
+        textdb.get_range(lo.as_bytes(), hi.as_bytes())
+            .map(|line| line.key().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(keys("C", "F"), ["C", "C", "D", "E"]);
+    assert_eq!(keys("F", "G"), ["F", "F", "F", "F", "F"]);
+    assert_eq!(keys("A", "L"), ["A", "B", "C", "C", "D", "E", "F", "F", "F", "F", "F", "G", "H", "I", "J", "K"]);
+    assert_eq!(keys("A", "M"), text.trim_end().split('\n').collect::<Vec<_>>());
+
+    // An empty range is genuinely empty, not a single empty line.
+    assert_eq!(keys("F", "F"), Vec::<String>::new());
+    // Nothing sorts below "A" or at/after "M".
+    assert_eq!(keys("0", "A"), Vec::<String>::new());
+    assert_eq!(keys("M", "Z"), Vec::<String>::new());
+    // Reversed bounds (lo > hi) are also an empty range, not a panic.
+    assert_eq!(keys("F", "A"), Vec::<String>::new());
+    assert_eq!(keys("L", "0"), Vec::<String>::new());
+}
+
+#[test]
+fn test_merged_table_iter() {
+    let accessor = accessor::TsvText::<0>::default();
+    let shards = [
+        "A\nC\nE\nG",
+        "B\nD\nF",
+        "H\n",
+    ];
+    let tables = shards.iter().map(|text| {
+        Table::new(maps::SafeMemoryMap::from_str(text), accessor)
+    }).collect::<Vec<_>>();
+
+    let merged = MergedTable::new(tables);
+    let keys = merged.iter().map(|line| line.key().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(keys, ["A", "B", "C", "D", "E", "F", "G", "H"]);
+}
+
+#[test]
+fn test_merged_table_iter_skips_empty_shard() {
+    // An empty shard (e.g. a day with no rows) used to contribute a
+    // phantom line with an empty key, since splitting empty input still
+    // yields one (empty) slice.
+    let accessor = accessor::TsvText::<0>::default();
+    let shards = ["", "b\tval\n"];
+    let tables = shards.iter().map(|text| {
+        Table::new(maps::SafeMemoryMap::from_str(text), accessor)
+    }).collect::<Vec<_>>();
+
+    let merged = MergedTable::new(tables);
+    let keys = merged.iter().map(|line| line.key().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(keys, ["b"]);
+}
+
+#[test]
+fn test_merged_table_get_matching_lines() {
+    let accessor = accessor::TsvText::<0>::default();
+    let shards = [
+        "A\t1\nC\t2\nC\t3\nE\t4",
+        "B\t5\nC\t6\nD\t7",
+    ];
+    // Plain tables, no Bloom filter: a key can be entirely absent from a
+    // shard (like "A" from the second shard below), and that shard's own
+    // binary search must come back with zero matches rather than panic.
+    let tables = shards.iter().map(|text| {
+        Table::new(maps::SafeMemoryMap::from_str(text), accessor)
+    }).collect::<Vec<_>>();
+
+    let merged = MergedTable::new(tables);
+    let values = merged.get_matching_lines("C".as_bytes())
+        .map(|line| line.col(1).unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(values, ["2", "3", "6"]);
+
+    // The first shard has one match; the second doesn't have the key at
+    // all and contributes nothing.
+    let a_values = merged.get_matching_lines("A".as_bytes())
+        .map(|line| line.col(1).unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(a_values, ["1"]);
+}
+
+#[test]
+fn test_merged_table_get_matching_lines_key_missing_from_one_shard() {
+    // A key present in one shard but entirely absent from another used to
+    // panic partway through that shard's binary search.
+    let accessor = accessor::TsvText::<0>::default();
+    let shards = ["A\nC\nE\n", "B\nD\nF\n"];
+    let tables = shards.iter().map(|text| {
+        Table::new(maps::SafeMemoryMap::from_str(text), accessor)
+    }).collect::<Vec<_>>();
+
+    let merged = MergedTable::new(tables);
+    let keys = merged.get_matching_lines("C".as_bytes())
+        .map(|line| line.key().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["C"]);
+
+    assert_eq!(merged.get_matching_lines("Z".as_bytes()).count(), 0);
+}
+
+#[test]
+fn test_table_builder_sorts_unsorted_input() {
+    let dir = std::env::temp_dir();
+    let input = dir.join(format!("textdb-test-builder-input-{}.tsv", std::process::id()));
+    let output = dir.join(format!("textdb-test-builder-output-{}.tsv", std::process::id()));
+
+    std::fs::write(&input, "C\t3\nA\t1\nE\t5\nB\t2\nD\t4\n").unwrap();
+
+    let accessor = accessor::TsvText::<0>::default();
+    // A tiny memory budget forces several sorted runs, so this also
+    // exercises the k-way merge, not just the in-memory sort of one chunk.
+    TableBuilder::new(accessor)
+        .with_memory_budget(8)
+        .with_temp_dir(&dir)
+        .build(&input, &output)
+        .unwrap();
+
+    let map = maps::SafeMemoryMap::from_file(&output).unwrap();
+    let textdb = Table::new(map, accessor);
+    assert!(textdb.is_sorted().unwrap());
+    assert_eq!(
+        textdb.keys().collect::<Result<Vec<_>, _>>().unwrap(),
+        ["A", "B", "C", "D", "E"]
+    );
+    assert_eq!(
+        textdb.cols(1).collect::<Result<Vec<_>, _>>().unwrap(),
+        ["1", "2", "3", "4", "5"]
+    );
+
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_scan_values() {
+    let accessor = accessor::TsvText::<0>::default();
+    let map = maps::SafeMemoryMap::from_str(
+        "A\tapple pie\nB\tbanana split\nC\tcherry cake\nD\tdate squares",
+    );
+    let textdb = Table::new(map, accessor);
+
+    let patterns: [&[u8]; 2] = [b"pie", b"cake"];
+    let keys = textdb
+        .scan_values(patterns, 1)
+        .map(|line| line.key().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["A", "C"]);
+
+    // No pattern appears in column 1, so nothing matches.
+    let none: [&[u8]; 1] = [b"zzz"];
+    assert_eq!(textdb.scan_values(none, 1).count(), 0);
+
+    // Patterns are only matched within the chosen column of one line:
+    // "pie\nB" occurs in the raw bytes, spanning from one line into the
+    // next, but never inside a single column.
+    let spanning: [&[u8]; 1] = [b"pie\nB"];
+    assert_eq!(textdb.scan_values(spanning, 1).count(), 0);
+}